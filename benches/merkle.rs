@@ -0,0 +1,108 @@
+/// Criterion bench harness over Merkle tree depth and circuit size `k`,
+/// modeled on upstream halo2's `benches/plonk.rs`. Reports `keygen_vk`,
+/// `keygen_pk`, `create_proof` and `verify_proof` timings plus the
+/// resulting proof length, so depth/`k` tradeoffs for a production login
+/// tree can be chosen with data instead of guesswork.
+use anonymous_login::backend::{self, IpaProver, IpaScheme, IpaStrategy, IpaVerifier};
+use anonymous_login::merkle_proof::MerkleSetup;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_proofs::pasta::Fp;
+use halo2_proofs::plonk::{keygen_pk, keygen_vk};
+
+// (depth, k): k must be large enough to fit `depth` Poseidon hashes plus
+// the Merkle chip's own rows; these pairs are practical starting points,
+// not requirements.
+const DEPTHS: &[(usize, u32)] = &[(4, 9), (20, 12), (32, 13)];
+
+fn sample_path(depth: usize) -> (Vec<Fp>, Vec<bool>) {
+    let path = (0..=depth).map(|i| Fp::from((i + 1) as u64)).collect();
+    let directions = (0..depth).map(|i| i % 2 == 0).collect();
+    (path, directions)
+}
+
+fn bench_keygen(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keygen");
+    for &(depth, k) in DEPTHS {
+        let params = backend::ipa_params(k);
+        // One compile to get a depth-shaped circuit (via `witness`, since
+        // `MyCircuit`'s fields are private) and a reference `vk` to reuse;
+        // this setup itself is not what's timed below.
+        let setup =
+            MerkleSetup::<IpaScheme>::compile(backend::ipa_params(k), depth).expect("compile should not fail");
+        let circuit = setup.witness(vec![Fp::zero(); depth + 1], vec![false; depth]);
+
+        group.bench_with_input(BenchmarkId::new("keygen_vk", depth), &depth, |b, _| {
+            b.iter(|| keygen_vk(&params, &circuit).expect("keygen_vk should not fail"));
+        });
+
+        group.bench_with_input(BenchmarkId::new("keygen_pk", depth), &depth, |b, _| {
+            b.iter(|| {
+                keygen_pk(&params, setup.vk.clone(), &circuit).expect("keygen_pk should not fail")
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_prove_verify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prove_verify");
+    for &(depth, k) in DEPTHS {
+        let params = backend::ipa_params(k);
+        let setup = MerkleSetup::<IpaScheme>::compile(params, depth).expect("compile should not fail");
+        let (path, directions) = sample_path(depth);
+        let mut root = path[0];
+        for (sibling, direction) in path[1..].iter().zip(&directions) {
+            // off-circuit companion to MerkleChip's left/right selection,
+            // just to get a valid instance for the bench circuit
+            use halo2_gadgets::poseidon::primitives::{ConstantLength, Hash, P128Pow5T3};
+            let (left, right) = if *direction {
+                (*sibling, root)
+            } else {
+                (root, *sibling)
+            };
+            root = Hash::<_, P128Pow5T3<Fp>, ConstantLength<2>, 3, 2>::init().hash([left, right]);
+        }
+
+        let circuit = setup.witness(path, directions);
+        let instances: &[&[&[Fp]]] = &[&[&[root]]];
+
+        group.bench_with_input(BenchmarkId::new("create_proof", depth), &depth, |b, _| {
+            b.iter(|| {
+                backend::prove::<IpaScheme, IpaProver, _>(
+                    &setup.params,
+                    &setup.pk,
+                    std::slice::from_ref(&circuit),
+                    instances,
+                )
+                .expect("proof generation should not fail")
+            });
+        });
+
+        let proof = backend::prove::<IpaScheme, IpaProver, _>(
+            &setup.params,
+            &setup.pk,
+            std::slice::from_ref(&circuit),
+            instances,
+        )
+        .expect("proof generation should not fail");
+        println!("depth={depth} k={k} proof length={} bytes", proof.len());
+
+        group.bench_with_input(BenchmarkId::new("verify_proof", depth), &depth, |b, _| {
+            b.iter(|| {
+                let strategy = IpaStrategy::new(&setup.params);
+                backend::verify::<IpaScheme, IpaVerifier, _>(
+                    &setup.params,
+                    &setup.vk,
+                    strategy,
+                    instances,
+                    &proof,
+                )
+                .expect("verification should not fail")
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_keygen, bench_prove_verify);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+/// Proving backends this crate can target: IPA over the Pasta curves (the
+/// existing MockProver/test path) or KZG/SHPLONK over BN256, which is what a
+/// Solidity verifier for on-chain anonymous login needs. `create_proof`/
+/// `verify_proof` are generic over [`halo2_proofs::poly::commitment::CommitmentScheme`]
+/// upstream; these helpers just pin the prover/verifier/strategy triple that
+/// goes with each scheme so callers don't have to repeat the type soup.
+use halo2_proofs::{
+    plonk::{create_proof, verify_proof, Circuit, Error, ProvingKey, VerifyingKey},
+    poly::{
+        commitment::{CommitmentScheme, Params, ParamsProver, Prover, Verifier},
+        ipa::{
+            commitment::{IPACommitmentScheme, ParamsIPA},
+            multiopen::{ProverIPA, VerifierIPA},
+            strategy::SingleStrategy as IpaSingleStrategy,
+        },
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverSHPLONK, VerifierSHPLONK},
+            strategy::SingleStrategy as KzgSingleStrategy,
+        },
+        VerificationStrategy,
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+};
+use halo2curves::{bn256::Bn256, pasta::EqAffine};
+use rand::rngs::OsRng;
+
+/// Generates a proof under commitment scheme `Scheme` using prover `P`,
+/// mirroring the `create_proof`/`Blake2bWrite` pairing the existing IPA
+/// tests use, just made generic over the scheme.
+pub fn prove<'params, Scheme, P, C>(
+    params: &'params Scheme::ParamsProver,
+    pk: &ProvingKey<Scheme::Curve>,
+    circuits: &[C],
+    instances: &[&[&[Scheme::Scalar]]],
+) -> Result<Vec<u8>, Error>
+where
+    Scheme: CommitmentScheme,
+    P: Prover<'params, Scheme>,
+    C: Circuit<Scheme::Scalar>,
+{
+    let mut transcript = Blake2bWrite::<_, Scheme::Curve, Challenge255<_>>::init(vec![]);
+    create_proof::<Scheme, P, _, _, _, _>(params, pk, circuits, instances, OsRng, &mut transcript)?;
+    Ok(transcript.finalize())
+}
+
+/// Verifies a proof produced by [`prove`] under the matching `Strategy`.
+pub fn verify<'params, Scheme, V, Strategy>(
+    params: &'params Scheme::ParamsVerifier,
+    vk: &VerifyingKey<Scheme::Curve>,
+    strategy: Strategy,
+    instances: &[&[&[Scheme::Scalar]]],
+    proof: &'params [u8],
+) -> Result<(), Error>
+where
+    Scheme: CommitmentScheme,
+    V: Verifier<'params, Scheme>,
+    Strategy: VerificationStrategy<'params, Scheme, V>,
+{
+    let mut transcript = Blake2bRead::<_, Scheme::Curve, Challenge255<_>>::init(proof);
+    verify_proof::<Scheme, V, _, _, _>(params, vk, strategy, instances, &mut transcript)?;
+    Ok(())
+}
+
+/// IPA over the Pasta curves (`EqAffine`), Halo2's original, recursion-friendly
+/// commitment scheme. No trusted setup; [`ipa_params`] derives `ParamsIPA`
+/// directly from `k`.
+pub type IpaScheme = IPACommitmentScheme<EqAffine>;
+pub type IpaProver<'params> = ProverIPA<'params>;
+pub type IpaVerifier<'params> = VerifierIPA<'params>;
+pub type IpaStrategy<'params> = IpaSingleStrategy<'params, EqAffine>;
+
+pub fn ipa_params(k: u32) -> ParamsIPA<EqAffine> {
+    ParamsIPA::new(k)
+}
+
+/// KZG/SHPLONK over BN256, compatible with the Solidity KZG/SHPLONK
+/// verifier used for on-chain anonymous-login proof checks. Requires a
+/// (dev) trusted setup via `ParamsKZG::setup`.
+pub type KzgScheme = KZGCommitmentScheme<Bn256>;
+pub type KzgProver<'params> = ProverSHPLONK<'params, Bn256>;
+pub type KzgVerifier<'params> = VerifierSHPLONK<'params, Bn256>;
+pub type KzgStrategy<'params> = KzgSingleStrategy<'params, Bn256>;
+
+pub fn kzg_params(k: u32) -> ParamsKZG<Bn256> {
+    ParamsKZG::setup(k, OsRng)
+}
@@ -0,0 +1,79 @@
+/// Thin wrapper around `halo2_gadgets`'s Poseidon permutation, fixed to the
+/// width-3/rate-2 `P128Pow5T3` spec so `MerkleChip` can hash a
+/// `(left, right)` pair down to a single parent node.
+use halo2_gadgets::poseidon::{
+    primitives::{ConstantLength, P128Pow5T3},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed},
+};
+
+pub type PoseidonConfig<F> = Pow5Config<F, 3, 2>;
+
+#[derive(Clone, Debug)]
+pub struct PoseidonChip<F: FieldExt> {
+    config: PoseidonConfig<F>,
+}
+
+impl<F: FieldExt> PoseidonChip<F> {
+    pub fn construct(config: PoseidonConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        state: [Column<Advice>; 3],
+        partial_sbox: Column<Advice>,
+        rc_a: [Column<Fixed>; 3],
+        rc_b: [Column<Fixed>; 3],
+    ) -> PoseidonConfig<F> {
+        Pow5Chip::configure::<P128Pow5T3<F>>(meta, state, partial_sbox, rc_a, rc_b)
+    }
+
+    /// Allocates the state/partial-sbox/round-constant columns a Poseidon
+    /// instance needs and configures them in one call, so chips that embed
+    /// Poseidon (`MerkleChip`, `MembershipChip`) don't each hand-roll the
+    /// same column wiring.
+    pub fn configure_columns(meta: &mut ConstraintSystem<F>) -> PoseidonConfig<F> {
+        let state = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let partial_sbox = meta.advice_column();
+        let rc_a = [
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+        ];
+        let rc_b = [
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+        ];
+        for column in state.iter().chain(Some(&partial_sbox)) {
+            meta.enable_equality(*column);
+        }
+        Self::configure(meta, state, partial_sbox, rc_a, rc_b)
+    }
+
+    /// Hash `(left, right)` and return the assigned digest cell, ready to be
+    /// copy-constrained into the next row's running node.
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<F>,
+        left: AssignedCell<F, F>,
+        right: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let chip = Pow5Chip::construct(self.config.clone());
+        let hasher = Hash::<_, _, P128Pow5T3<F>, _, 3, 2>::init(
+            chip,
+            layouter.namespace(|| "init poseidon"),
+            ConstantLength::<2>,
+        )?;
+        hasher.hash(layouter.namespace(|| "hash(left, right)"), [left, right])
+    }
+}
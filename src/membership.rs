@@ -0,0 +1,292 @@
+/// Set-membership circuit: proves a private leaf is one of the allowlist's
+/// member hashes via a dynamic lookup argument against an advice-column
+/// table, instead of re-deriving a Merkle path. Adding or removing a member
+/// only means re-filling `table`; proof depth stays O(1) regardless of how
+/// many members the allowlist holds.
+use crate::gadgets::poseidon::{PoseidonChip, PoseidonConfig};
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct MembershipConfig<F: FieldExt> {
+    /// The private leaf being proven a member of `table`.
+    pub leaf: Column<Advice>,
+    pub s_leaf: Selector,
+    /// One authorized member hash per enabled row.
+    pub table: Column<Advice>,
+    pub s_table: Selector,
+    /// Scratch column used to re-materialize table members when folding
+    /// them into the table commitment, so the commitment provably covers
+    /// the same cells the lookup checks against.
+    pub fold: Column<Advice>,
+    pub instance: Column<Instance>,
+    pub poseidon_config: PoseidonConfig<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MembershipChip<F: FieldExt> {
+    config: MembershipConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MembershipChip<F> {
+    pub fn construct(config: MembershipConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MembershipConfig<F> {
+        let leaf = meta.advice_column();
+        let s_leaf = meta.complex_selector();
+        let table = meta.advice_column();
+        let s_table = meta.complex_selector();
+        let fold = meta.advice_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(leaf);
+        meta.enable_equality(table);
+        meta.enable_equality(fold);
+        meta.enable_equality(instance);
+
+        // Keyed on (selector, selector*value) pairs rather than a single
+        // selector*value expression: collapsing to one expression lets a
+        // disabled row (selector = 0) and an enabled leaf of exactly 0 both
+        // reduce to 0 and match each other, forging membership for leaf = 0
+        // even when 0 was never loaded into the table. Carrying the
+        // selector itself as a lookup column means a disabled table row can
+        // only match an equally "disabled" leaf row, never an active one.
+        meta.lookup_any("leaf is a member of the allowlist table", |meta| {
+            let s_leaf = meta.query_selector(s_leaf);
+            let leaf = meta.query_advice(leaf, Rotation::cur());
+            let s_table = meta.query_selector(s_table);
+            let table = meta.query_advice(table, Rotation::cur());
+            vec![
+                (s_leaf.clone(), s_table.clone()),
+                (s_leaf * leaf, s_table * table),
+            ]
+        });
+
+        let poseidon_config = PoseidonChip::configure_columns(meta);
+
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        MembershipConfig {
+            leaf,
+            s_leaf,
+            table,
+            s_table,
+            fold,
+            instance,
+            poseidon_config,
+        }
+    }
+
+    /// Fills the allowlist table, one member hash per row, and returns the
+    /// assigned cells so [`MembershipChip::commit_table`] can fold the same
+    /// cells into a commitment.
+    pub fn load_table(
+        &self,
+        mut layouter: impl Layouter<F>,
+        members: &[F],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "allowlist table",
+            |mut region| {
+                members
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, member)| {
+                        self.config.s_table.enable(&mut region, offset)?;
+                        region.assign_advice(
+                            || "member",
+                            self.config.table,
+                            offset,
+                            || Value::known(*member),
+                        )
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Assigns the private leaf checked against the table by the lookup
+    /// argument configured in [`MembershipChip::configure`].
+    pub fn assign_leaf(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: F,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "leaf",
+            |mut region| {
+                self.config.s_leaf.enable(&mut region, 0)?;
+                region.assign_advice(|| "leaf", self.config.leaf, 0, || Value::known(leaf))
+            },
+        )
+    }
+
+    /// Folds the table's member cells into a single Poseidon commitment,
+    /// `commitment = Poseidon(...Poseidon(Poseidon(0, m_0), m_1)..., m_n)`,
+    /// re-materializing each member cell via a copy constraint so the
+    /// commitment covers exactly the cells the lookup checked against.
+    pub fn commit_table(
+        &self,
+        mut layouter: impl Layouter<F>,
+        members: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let poseidon_chip = PoseidonChip::construct(self.config.poseidon_config.clone());
+
+        // Pinned to 0 via the fixed-column constant constraint enabled in
+        // `configure`, not a bare advice assignment, so the fold's starting
+        // point is fixed by the circuit rather than chosen by the prover.
+        let mut acc = layouter.assign_region(
+            || "table commitment seed",
+            |mut region| {
+                region.assign_advice_from_constant(|| "seed", self.config.fold, 0, F::zero())
+            },
+        )?;
+
+        for (i, member) in members.iter().enumerate() {
+            let member_cell = layouter.assign_region(
+                || format!("member {i} operand"),
+                |mut region| member.copy_advice(|| "member", &mut region, self.config.fold, 0),
+            )?;
+            acc = poseidon_chip.hash(
+                layouter.namespace(|| format!("commit member {i}")),
+                acc,
+                member_cell,
+            )?;
+        }
+        Ok(acc)
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default)]
+struct MembershipCircuit<F> {
+    // private input: the full allowlist, one member hash per row
+    members: Vec<F>,
+    // private input: the leaf being proven a member; length 0 or 1, like
+    // `path` in `merkle_proof::MyCircuit`, so `without_witnesses` stays a
+    // plain default
+    leaf: Vec<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MembershipCircuit<F> {
+    type Config = MembershipConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            members: vec![],
+            leaf: vec![],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MembershipChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = MembershipChip::construct(config);
+
+        let table_cells = chip.load_table(layouter.namespace(|| "load table"), &self.members)?;
+        chip.assign_leaf(layouter.namespace(|| "leaf"), self.leaf[0])?;
+        let commitment =
+            chip.commit_table(layouter.namespace(|| "commit table"), &table_cells)?;
+        //only public input is the allowlist commitment
+        chip.expose_public(layouter.namespace(|| "out"), &commitment, 0)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength, P128Pow5T3};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // Off-circuit equivalent of `MembershipChip::commit_table`.
+    fn table_commitment(members: &[Fp]) -> Fp {
+        let mut acc = Fp::zero();
+        for member in members {
+            acc = poseidon::Hash::<_, P128Pow5T3<Fp>, ConstantLength<2>, 3, 2>::init()
+                .hash([acc, *member]);
+        }
+        acc
+    }
+
+    #[test]
+    fn membership_holds_for_listed_leaf() {
+        let k = 8;
+        let members = vec![Fp::from(11), Fp::from(26), Fp::from(37)];
+        let commitment = table_commitment(&members);
+        let circuit = MembershipCircuit {
+            members,
+            leaf: vec![Fp::from(26)],
+        };
+
+        let prover = MockProver::run(k, &circuit, vec![vec![commitment]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn membership_fails_for_unlisted_leaf() {
+        let k = 8;
+        let members = vec![Fp::from(11), Fp::from(26), Fp::from(37)];
+        let commitment = table_commitment(&members);
+        let circuit = MembershipCircuit {
+            members,
+            leaf: vec![Fp::from(99)],
+        };
+
+        let prover = MockProver::run(k, &circuit, vec![vec![commitment]]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn membership_fails_for_zero_leaf_not_in_table() {
+        // Regression test: a leaf of exactly 0 must not match a disabled
+        // lookup-table row just because both sides collapse to 0.
+        let k = 8;
+        let members = vec![Fp::from(11), Fp::from(26), Fp::from(37)];
+        let commitment = table_commitment(&members);
+        let circuit = MembershipCircuit {
+            members,
+            leaf: vec![Fp::zero()],
+        };
+
+        let prover = MockProver::run(k, &circuit, vec![vec![commitment]]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn membership_fails_on_wrong_commitment() {
+        let k = 8;
+        let members = vec![Fp::from(11), Fp::from(26), Fp::from(37)];
+        let wrong_commitment = table_commitment(&members) + Fp::one();
+        let circuit = MembershipCircuit {
+            members,
+            leaf: vec![Fp::from(26)],
+        };
+
+        let prover = MockProver::run(k, &circuit, vec![vec![wrong_commitment]]).unwrap();
+        assert_ne!(prover.verify(), Ok(()));
+    }
+}
@@ -1,20 +1,43 @@
-/// Starting with + operation
-/// Ultimately + will be replaced by Hash operation
+/// Merkle membership circuit: each row hashes the running node with a
+/// sibling via Poseidon to derive the parent, chaining up to the root.
 use crate::gadgets::is_zero::{IsZeroChip, IsZeroConfig};
-use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+use crate::gadgets::poseidon::{PoseidonChip, PoseidonConfig};
+use halo2_proofs::{
+    arithmetic::FieldExt, circuit::*, plonk::*, poly::commitment::CommitmentScheme, poly::Rotation,
+};
 use std::marker::PhantomData;
+
 #[derive(Debug, Clone)]
-struct MerkleConfig<F> {
-    pub col_a: Column<Advice>,
-    pub col_b: Column<Advice>,
-    pub col_c: Column<Advice>,
-    pub selector: Selector,
+pub struct MerkleConfig<F: FieldExt> {
+    /// Running node value: holds the leaf, then each level's input node; the
+    /// root is the last level's `node_out` cell, bound to the public instance.
+    pub node: Column<Advice>,
+    /// Sibling hash at the current level; zero means a zero-padding row.
+    pub sibling: Column<Advice>,
+    /// Boolean: 0 if `node` is the left child, 1 if it is the right child.
+    pub direction: Column<Advice>,
+    /// `left`/`right` operands fed to the Poseidon gadget, derived from
+    /// `node`/`sibling`/`direction`.
+    pub left: Column<Advice>,
+    pub right: Column<Advice>,
+    /// Holds `Poseidon(left, right)` for the current level.
+    pub hash_out: Column<Advice>,
+    /// The parent node: `node` if `sibling == 0` (zero padding), else
+    /// `hash_out`.
+    pub node_out: Column<Advice>,
+    /// Enables the `direction`-boolean and `left`/`right` derivation gates.
+    pub s_operands: Selector,
+    /// Enables the `node_out` selection gate.
+    pub s_digest: Selector,
     pub instance: Column<Instance>,
     pub is_zero: IsZeroConfig<F>,
+    pub poseidon_config: PoseidonConfig<F>,
+    /// Tree depth (number of sibling levels) this config was sized for.
+    pub depth: usize,
 }
 
 #[derive(Debug, Clone)]
-struct MerkleChip<F: FieldExt> {
+pub struct MerkleChip<F: FieldExt> {
     config: MerkleConfig<F>,
     _marker: PhantomData<F>,
 }
@@ -27,48 +50,98 @@ impl<F: FieldExt> MerkleChip<F> {
         }
     }
 
-    pub fn configure(meta: &mut ConstraintSystem<F>) -> MerkleConfig<F> {
-        let col_a = meta.advice_column();
-        let col_b = meta.advice_column();
-        let col_c = meta.advice_column();
-        let selector = meta.selector();
+    /// `depth` is the number of sibling levels (and thus Poseidon hashes)
+    /// the resulting config/keys are sized for; it is part of the circuit's
+    /// [`MerkleCircuitParams`] and must match the `directions` length later
+    /// passed to [`MerkleChip::assign`].
+    pub fn configure(meta: &mut ConstraintSystem<F>, depth: usize) -> MerkleConfig<F> {
+        let node = meta.advice_column();
+        let sibling = meta.advice_column();
+        let direction = meta.advice_column();
+        let left = meta.advice_column();
+        let right = meta.advice_column();
+        let hash_out = meta.advice_column();
+        let node_out = meta.advice_column();
+        let s_operands = meta.selector();
+        let s_digest = meta.selector();
         let instance = meta.instance_column();
 
-        let value_inv = meta.advice_column();
+        let sibling_inv = meta.advice_column();
         let is_zero = IsZeroChip::configure(
             meta,
-            |meta| meta.query_selector(selector),
-            |meta| meta.query_advice(col_b, Rotation::cur()),
-            value_inv,
+            |meta| meta.query_selector(s_digest),
+            |meta| meta.query_advice(sibling, Rotation::cur()),
+            sibling_inv,
         );
 
-        meta.enable_equality(col_a);
-        meta.enable_equality(col_b);
-        meta.enable_equality(col_c);
+        meta.enable_equality(node);
+        meta.enable_equality(sibling);
+        meta.enable_equality(direction);
+        meta.enable_equality(left);
+        meta.enable_equality(right);
+        meta.enable_equality(hash_out);
+        meta.enable_equality(node_out);
         meta.enable_equality(instance);
 
-        meta.create_gate("c = if b == 0 { a } else { a + b }", |meta| {
+        meta.create_gate("direction is boolean", |meta| {
+            let s = meta.query_selector(s_operands);
+            let direction = meta.query_advice(direction, Rotation::cur());
+            vec![s * direction.clone() * (Expression::Constant(F::one()) - direction)]
+        });
+
+        meta.create_gate("left/right are node/sibling ordered by direction", |meta| {
             //
-            // col_a | col_b | col_c | selector | instance
-            //   a      b        c       s            i
+            // node | sibling | direction | left | right | s_operands
             //
-            let s = meta.query_selector(selector);
-            let a = meta.query_advice(col_a, Rotation::cur());
-            let b = meta.query_advice(col_b, Rotation::cur());
-            let c = meta.query_advice(col_c, Rotation::cur());
+            // direction = 0 => node is the left child, sibling is the right
+            // direction = 1 => node is the right child, sibling is the left
+            let s = meta.query_selector(s_operands);
+            let node = meta.query_advice(node, Rotation::cur());
+            let sibling = meta.query_advice(sibling, Rotation::cur());
+            let direction = meta.query_advice(direction, Rotation::cur());
+            let left = meta.query_advice(left, Rotation::cur());
+            let right = meta.query_advice(right, Rotation::cur());
+            vec![
+                s.clone()
+                    * (left - (node.clone() + direction.clone() * (sibling.clone() - node.clone()))),
+                s * (right - (sibling.clone() + direction * (node - sibling))),
+            ]
+        });
+
+        meta.create_gate("node_out = is_zero(sibling) ? node : hash_out", |meta| {
+            // Re-materialized (via copy constraints) in the same row as
+            // `hash_out`, so `sibling == 0` zero-padding rows pass `node`
+            // through unchanged instead of being hashed.
+            let s = meta.query_selector(s_digest);
+            let node = meta.query_advice(node, Rotation::cur());
+            let hash_out = meta.query_advice(hash_out, Rotation::cur());
+            let node_out = meta.query_advice(node_out, Rotation::cur());
+            let skip = is_zero.expr();
             vec![
-                s.clone() * is_zero.expr() * (a.clone() - c.clone()),
-                s * (Expression::Constant(F::one()) - is_zero.expr()) * (a + b - c),
+                s * (node_out - (skip.clone() * node + (Expression::Constant(F::one()) - skip) * hash_out)),
             ]
         });
 
+        let poseidon_config = PoseidonChip::configure_columns(meta);
+
+        // The Poseidon gadget assigns its initial state from constants.
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
         MerkleConfig {
-            col_a,
-            col_b,
-            col_c,
-            selector,
+            node,
+            sibling,
+            direction,
+            left,
+            right,
+            hash_out,
+            node_out,
+            s_operands,
+            s_digest,
             instance,
             is_zero,
+            poseidon_config,
+            depth,
         }
     }
     //                36
@@ -78,64 +151,126 @@ impl<F: FieldExt> MerkleChip<F> {
     //   3       7       11      15
     //  / \     / \     / \     / \
     // 1   2   3   4   5   6   7   8
-    // to prove the membership of 1 the path is 1, 2, 7, 26
+    // to prove the membership of 1 the path is [1, 2, 7, 26] with
+    // directions [false, false, false] (1 is the left child at every level)
     //
+    /// `path[0]` is the leaf; `path[1..]` are the sibling hashes from the
+    /// leaf's level up to the root, and `directions[i]` says whether `path[0]`'s
+    /// running node is the left (`false`) or right (`true`) child when
+    /// combined with `path[i + 1]`. A zero sibling marks a padding row: the
+    /// node passes through unchanged instead of being hashed.
     #[allow(clippy::type_complexity)]
     pub fn assign(
         &self,
         mut layouter: impl Layouter<F>,
         path: Vec<F>,
+        directions: Vec<bool>,
     ) -> Result<AssignedCell<F, F>, Error> {
-        layouter.assign_region(
-            || "entire table, c = if b==0 {a} else {a+b}",
+        assert_eq!(
+            path.len(),
+            directions.len() + 1,
+            "one direction bit per sibling"
+        );
+        assert_eq!(
+            directions.len(),
+            self.config.depth,
+            "path depth does not match the depth this config was configured for"
+        );
+        let poseidon_chip = PoseidonChip::construct(self.config.poseidon_config.clone());
+        let is_zero_chip = IsZeroChip::construct(self.config.is_zero.clone());
+
+        let mut node_cell = layouter.assign_region(
+            || "leaf",
             |mut region| {
-                let is_zero_chip = IsZeroChip::construct(self.config.is_zero.clone());
-
-                self.config.selector.enable(&mut region, 0)?;
-                let mut a_cell =
-                    region.assign_advice(|| "a", self.config.col_a, 0, || Value::known(path[0]))?;
-
-                // b = 0; // in first row
-                let mut b_cell = region.assign_advice(
-                    || "b",
-                    self.config.col_b,
-                    0,
-                    || Value::known(F::zero()),
-                )?;
-
-                let mut c_cell = region.assign_advice(
-                    || "a + b",
-                    self.config.col_c,
-                    0,
-                    || a_cell.value().copied(),
-                )?;
-                is_zero_chip.assign(&mut region, 0, b_cell.value().copied())?;
-
-                for row in 1..(path.len()) {
-                    self.config.selector.enable(&mut region, row)?;
-
-                    // Copy the value from c in previous row to a in current row
-                    a_cell = c_cell.copy_advice(|| "a", &mut region, self.config.col_a, row)?;
-
-                    b_cell = region.assign_advice(
-                        || "b",
-                        self.config.col_b,
-                        row,
-                        || Value::known(path[row]),
+                region.assign_advice(|| "leaf", self.config.node, 0, || Value::known(path[0]))
+            },
+        )?;
+
+        // Each level: (1) derive `left`/`right` from `node`/`sibling`/`direction`
+        // in one region, (2) feed them through the Poseidon gadget, which
+        // manages its own regions, then (3) re-materialize `node`/`hash_out`
+        // in a closing region to select the next level's node.
+        for row in 0..directions.len() {
+            let sibling_value = path[row + 1];
+            let direction = directions[row];
+            let direction_value = if direction { F::one() } else { F::zero() };
+
+            let (left_cell, right_cell, sibling_cell) = layouter.assign_region(
+                || format!("level {row} operands"),
+                |mut region| {
+                    self.config.s_operands.enable(&mut region, 0)?;
+                    let node_in =
+                        node_cell.copy_advice(|| "node", &mut region, self.config.node, 0)?;
+                    let sibling_in = region.assign_advice(
+                        || "sibling",
+                        self.config.sibling,
+                        0,
+                        || Value::known(sibling_value),
+                    )?;
+                    region.assign_advice(
+                        || "direction",
+                        self.config.direction,
+                        0,
+                        || Value::known(direction_value),
                     )?;
-                    is_zero_chip.assign(&mut region, row, b_cell.value().copied())?;
 
-                    let value = if path[row] == F::zero() {
-                        a_cell.value().copied()
+                    // direction = 0: node is left, sibling is right.
+                    // direction = 1: node is right, sibling is left.
+                    let (left_value, right_value) = if direction {
+                        (sibling_in.value().copied(), node_in.value().copied())
                     } else {
-                        a_cell.value().copied() + b_cell.value()
+                        (node_in.value().copied(), sibling_in.value().copied())
                     };
-
-                    c_cell = region.assign_advice(|| "c", self.config.col_c, row, || value)?;
-                }
-                Ok(c_cell)
-            },
-        )
+                    let left_cell =
+                        region.assign_advice(|| "left", self.config.left, 0, || left_value)?;
+                    let right_cell =
+                        region.assign_advice(|| "right", self.config.right, 0, || right_value)?;
+                    Ok((left_cell, right_cell, sibling_in))
+                },
+            )?;
+
+            let hash_cell = poseidon_chip.hash(
+                layouter.namespace(|| format!("level {row} poseidon")),
+                left_cell,
+                right_cell,
+            )?;
+
+            node_cell = layouter.assign_region(
+                || format!("level {row} parent"),
+                |mut region| {
+                    self.config.s_digest.enable(&mut region, 0)?;
+                    let node_in =
+                        node_cell.copy_advice(|| "node", &mut region, self.config.node, 0)?;
+                    // Copy the same sibling cell the operands region fed
+                    // into left/right, rather than re-assigning a fresh
+                    // witness value here: otherwise nothing ties the
+                    // is_zero-gated sibling to the one that actually
+                    // produced hash_out, and a prover could set this row's
+                    // sibling to 0 to skip hashing while still using a
+                    // nonzero sibling above.
+                    let sibling_in = sibling_cell.copy_advice(
+                        || "sibling",
+                        &mut region,
+                        self.config.sibling,
+                        0,
+                    )?;
+                    is_zero_chip.assign(&mut region, 0, sibling_in.value().copied())?;
+                    let hash_in = hash_cell.copy_advice(
+                        || "hash_out",
+                        &mut region,
+                        self.config.hash_out,
+                        0,
+                    )?;
+                    let next_value = if sibling_value.is_zero_vartime() {
+                        node_in.value().copied()
+                    } else {
+                        hash_in.value().copied()
+                    };
+                    region.assign_advice(|| "node_out", self.config.node_out, 0, || next_value)
+                },
+            )?;
+        }
+        Ok(node_cell)
     }
 
     pub fn expose_public(
@@ -148,23 +283,54 @@ impl<F: FieldExt> MerkleChip<F> {
     }
 }
 
+/// Configure-time parameter carrying the Merkle tree depth, following the
+/// `circuit-params` pattern: the constraint system (and thus the proving
+/// and verifying keys) is sized for one depth, generated once per
+/// deployment rather than hardcoded in source.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MerkleCircuitParams {
+    /// Number of sibling levels in the tree, e.g. 20 or 32 for a
+    /// production login tree.
+    pub depth: usize,
+}
+
 #[derive(Default)]
-struct MyCircuit<F> {
-    // private input
+pub struct MyCircuit<F> {
+    // private input: the leaf followed by each level's sibling
     path: Vec<F>,
+    // private input: left/right child bit for each sibling in `path[1..]`
+    directions: Vec<bool>,
 }
 
 impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
     type Config = MerkleConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
+    type Params = MerkleCircuitParams;
 
     fn without_witnesses(&self) -> Self {
-        // Self::default()
-        Self { path: vec![] }
+        Self {
+            path: vec![],
+            directions: vec![],
+        }
+    }
+
+    fn params(&self) -> Self::Params {
+        MerkleCircuitParams {
+            depth: self.directions.len(),
+        }
+    }
+
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<F>,
+        params: Self::Params,
+    ) -> Self::Config {
+        MerkleChip::configure(meta, params.depth)
     }
 
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        MerkleChip::configure(meta)
+    fn configure(_meta: &mut ConstraintSystem<F>) -> Self::Config {
+        unreachable!(
+            "MyCircuit's depth is a configure-time param; use configure_with_params instead"
+        )
     }
 
     fn synthesize(
@@ -174,49 +340,128 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
     ) -> Result<(), Error> {
         let chip = MerkleChip::construct(config);
 
-        let c_cell = chip.assign(layouter.namespace(|| "entire table 1"), self.path.clone())?;
+        let root_cell = chip.assign(
+            layouter.namespace(|| "merkle path"),
+            self.path.clone(),
+            self.directions.clone(),
+        )?;
         //only public input is the root hash
-        chip.expose_public(layouter.namespace(|| "out"), &c_cell, 0)?;
+        chip.expose_public(layouter.namespace(|| "out"), &root_cell, 0)?;
 
         Ok(())
     }
 }
 
+/// Frontend/backend split: compile the constraint system and run
+/// `keygen_vk`/`keygen_pk` once per tree depth at server startup, then call
+/// [`MerkleSetup::witness`] cheaply per login to build the `MyCircuit` for
+/// that user's path. Passing several such circuits as one `&[circuit]`
+/// slice to `backend::prove` batches them into a single `create_proof`
+/// call instead of one proof per login.
+///
+/// Deliberately a free-standing type rather than methods on `MerkleChip`:
+/// `pk`/`vk` are keyed on a `CommitmentScheme`, not on `F: FieldExt` like
+/// `MerkleChip`, so they don't fit `MerkleChip`'s generic parameter. This is
+/// the chosen replacement for a `MerkleChip::compile`/`MerkleChip::witness`
+/// API, not an accidental divergence from it.
+pub struct MerkleSetup<Scheme: CommitmentScheme>
+where
+    Scheme::Scalar: FieldExt,
+{
+    pub depth: usize,
+    pub params: Scheme::ParamsProver,
+    pub pk: ProvingKey<Scheme::Curve>,
+    pub vk: VerifyingKey<Scheme::Curve>,
+}
+
+impl<Scheme: CommitmentScheme> MerkleSetup<Scheme>
+where
+    Scheme::Scalar: FieldExt,
+{
+    pub fn compile(params: Scheme::ParamsProver, depth: usize) -> Result<Self, Error> {
+        let empty_circuit: MyCircuit<Scheme::Scalar> = MyCircuit {
+            path: vec![Scheme::Scalar::zero(); depth + 1],
+            directions: vec![false; depth],
+        };
+        let vk = keygen_vk(&params, &empty_circuit)?;
+        let pk = keygen_pk(&params, vk.clone(), &empty_circuit)?;
+        Ok(Self {
+            depth,
+            params,
+            pk,
+            vk,
+        })
+    }
+
+    /// Builds the per-login witness circuit for `path`/`directions` against
+    /// this compiled setup. Unlike [`MerkleSetup::compile`], this does no
+    /// constraint-system work, so it's cheap enough to call once per proof
+    /// request.
+    pub fn witness(
+        &self,
+        path: Vec<Scheme::Scalar>,
+        directions: Vec<bool>,
+    ) -> MyCircuit<Scheme::Scalar> {
+        assert_eq!(
+            directions.len(),
+            self.depth,
+            "path depth must match the depth this setup was compiled for"
+        );
+        MyCircuit { path, directions }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    // use std::marker::PhantomData;
-
     use core::panic;
 
     use super::*;
-    use halo2_proofs::{
-        dev::MockProver,
-        pasta::{EqAffine, Fp},
-        poly::commitment::Params,
-        transcript::{Blake2bRead, Blake2bWrite, Challenge255},
-    };
-    use rand::rngs::OsRng;
-    // a struct to hold the common setup between prover and verifier
-    pub struct TestEnvironment {
+    use crate::backend::{self, IpaProver, IpaScheme, IpaStrategy, IpaVerifier};
+    use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength, P128Pow5T3};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // Off-circuit Poseidon(left, right), mirroring `MerkleChip::assign`'s
+    // selection of left/right by direction bit, so tests don't hardcode
+    // hash outputs.
+    fn merkle_root(leaf: Fp, siblings: &[Fp], directions: &[bool]) -> Fp {
+        let mut node = leaf;
+        for (&sibling, &direction) in siblings.iter().zip(directions) {
+            if sibling.is_zero_vartime() {
+                continue;
+            }
+            let (left, right) = if direction {
+                (sibling, node)
+            } else {
+                (node, sibling)
+            };
+            node = poseidon::Hash::<_, P128Pow5T3<Fp>, ConstantLength<2>, 3, 2>::init()
+                .hash([left, right]);
+        }
+        node
+    }
+
+    // Holds the common setup between prover and verifier, generic over the
+    // commitment scheme. Only an IPA/Pasta instance is built here
+    // (`setup_ipa`); the KZG test runs its own keygen.
+    pub struct TestEnvironment<Scheme: CommitmentScheme> {
         k: u32,
-        pk: ProvingKey<EqAffine>,
-        vk: VerifyingKey<EqAffine>,
-        params: Params<EqAffine>,
+        pk: ProvingKey<Scheme::Curve>,
+        vk: VerifyingKey<Scheme::Curve>,
+        params: Scheme::ParamsProver,
     }
-    // Helper function to initialize the common environment
-    fn setup() -> TestEnvironment {
-        let k = 4;
-        // Generate proving and verfication keys on dummy circuit
-        let params: Params<EqAffine> = Params::new(k);
-        let v = Fp::zero();
-        // path has to be of fixed len. say 4
+
+    // Helper function to initialize the common IPA/Pasta environment
+    fn setup_ipa() -> TestEnvironment<IpaScheme> {
+        let k = 8;
+        let params = backend::ipa_params(k);
+        // path has to be of fixed len. say 4 (leaf + 3 siblings)
         let empty_circuit: MyCircuit<Fp> = MyCircuit {
-            path: [v; 4].into(),
+            path: vec![Fp::zero(); 4],
+            directions: vec![false; 3],
         };
         let vk = keygen_vk(&params, &empty_circuit).expect("keygen_vk should not fail");
         let pk = keygen_pk(&params, vk.clone(), &empty_circuit).expect("keygen_pk should not fail");
 
-        // Perform setup here and return the TestEnvironment instance
         TestEnvironment { k, pk, vk, params }
     }
     #[test]
@@ -225,13 +470,13 @@ mod tests {
         let b = Fp::from(2); // F[1]
         let c = Fp::from(7); // F[2]
         let d = Fp::from(26); // F[3]
-        let common_env = setup();
+        let common_env = setup_ipa();
 
-        // let params: Params<EqAffine> = Params::new(common_env.k);
-        let root = Fp::from(36); // F[4]
-        let public_input = vec![root];
         let path = vec![a, b, c, d];
-        let circuit = MyCircuit { path };
+        let directions = vec![false, false, true];
+        let root = merkle_root(path[0], &path[1..], &directions);
+        let public_input = vec![root];
+        let circuit = MyCircuit { path, directions };
         //mock test if this circuit is satisfied
         let prover = match MockProver::run(common_env.k, &circuit, vec![public_input.clone()]) {
             Ok(prover) => prover,
@@ -239,29 +484,22 @@ mod tests {
         };
         prover.assert_satisfied();
 
-        let mut transcript: Blake2bWrite<_, _, Challenge255<_>> =
-            Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-
-        create_proof(
+        let proof = backend::prove::<IpaScheme, IpaProver, _>(
             &common_env.params,
             &common_env.pk,
             &[circuit],
             &[&[&[root]]],
-            OsRng,
-            &mut transcript,
         )
         .expect("proof generation should not fail");
-        let proof: Vec<u8> = transcript.finalize();
         println!("proof length:{}", proof.len());
 
-        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
-        let strategy = SingleVerifier::new(&common_env.params);
-        assert!(verify_proof(
+        let strategy = IpaStrategy::new(&common_env.params);
+        assert!(backend::verify::<IpaScheme, IpaVerifier, _>(
             &common_env.params,
             &common_env.vk,
             strategy,
             &[&[&public_input[..]]],
-            &mut transcript,
+            &proof,
         )
         .is_ok());
     }
@@ -271,14 +509,15 @@ mod tests {
         let b = Fp::from(2); // F[1]
         let c = Fp::from(7); // F[2]
         let d = Fp::zero(); // F[3]  // pad to make same length
-        let root = Fp::from(10); // F[4]
-        let common_env = setup();
+        let common_env = setup_ipa();
 
-        // verify smaller
-        let public_input = vec![root];
-        // pad with field value zero to meet the path length
+        // pad with field value zero to meet the path length; the final
+        // padding row passes the node through unchanged
         let path = vec![a, b, c, d];
-        let circuit = MyCircuit { path };
+        let directions = vec![false, false, false];
+        let root = merkle_root(path[0], &path[1..], &directions);
+        let public_input = vec![root];
+        let circuit = MyCircuit { path, directions };
         //mock test if this circuit is satisfied
         let prover = match MockProver::run(common_env.k, &circuit, vec![public_input.clone()]) {
             Ok(prover) => prover,
@@ -286,46 +525,126 @@ mod tests {
         };
         prover.assert_satisfied();
 
-        let mut transcript: Blake2bWrite<_, _, Challenge255<_>> =
-            Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-
-        create_proof(
+        let proof = backend::prove::<IpaScheme, IpaProver, _>(
             &common_env.params,
             &common_env.pk,
             &[circuit],
             &[&[&[root]]],
-            OsRng,
-            &mut transcript,
         )
         .expect("proof generation should not fail");
-        let proof: Vec<u8> = transcript.finalize();
         println!("proof length:{}", proof.len());
 
-        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
-        let strategy = SingleVerifier::new(&common_env.params);
-        assert!(verify_proof(
+        let strategy = IpaStrategy::new(&common_env.params);
+        assert!(backend::verify::<IpaScheme, IpaVerifier, _>(
             &common_env.params,
             common_env.pk.get_vk(),
             strategy,
             &[&[&public_input[..]]],
-            &mut transcript,
+            &proof,
         )
         .is_ok());
     }
 
     #[test]
-    fn merkle_example1() {
-        let k = 4;
+    fn merkle_batch_proof_two_paths() {
+        // leaf=1, siblings=2,7,26, directions=[false,false,true] -> root
+        let path_a = vec![Fp::from(1), Fp::from(2), Fp::from(7), Fp::from(26)];
+        let directions_a = vec![false, false, true];
+        // a second, distinct path sharing the same depth
+        let path_b = vec![Fp::from(8), Fp::from(5), Fp::from(11), Fp::from(26)];
+        let directions_b = vec![true, false, true];
+
+        let setup = MerkleSetup::<IpaScheme>::compile(backend::ipa_params(8), 3)
+            .expect("setup should not fail");
+
+        let root_a = merkle_root(path_a[0], &path_a[1..], &directions_a);
+        let root_b = merkle_root(path_b[0], &path_b[1..], &directions_b);
+        let circuit_a = setup.witness(path_a, directions_a);
+        let circuit_b = setup.witness(path_b, directions_b);
+        let instances = vec![vec![root_a], vec![root_b]];
+
+        let proof = backend::prove::<IpaScheme, IpaProver, _>(
+            &setup.params,
+            &setup.pk,
+            &[circuit_a, circuit_b],
+            &[&[&[root_a]], &[&[root_b]]],
+        )
+        .expect("batched proof generation should not fail");
+        println!("batched proof length:{}", proof.len());
+
+        let strategy = IpaStrategy::new(&setup.params);
+        assert!(backend::verify::<IpaScheme, IpaVerifier, _>(
+            &setup.params,
+            &setup.vk,
+            strategy,
+            &[&[&instances[0][..]], &[&instances[1][..]]],
+            &proof,
+        )
+        .is_ok());
+    }
+
+    #[cfg(feature = "kzg")]
+    #[test]
+    fn merkle_generate_proof_kzg() {
+        use crate::backend::{KzgProver, KzgScheme, KzgStrategy, KzgVerifier};
 
         let a = Fp::from(1); // F[0]
         let b = Fp::from(2); // F[1]
         let c = Fp::from(7); // F[2]
         let d = Fp::from(26); // F[3]
-        let root = Fp::from(36); // F[4]
 
+        let k = 8;
+        let params = backend::kzg_params(k);
+        let empty_circuit: MyCircuit<Fp> = MyCircuit {
+            path: vec![Fp::zero(); 4],
+            directions: vec![false; 3],
+        };
+        let vk = keygen_vk(&params, &empty_circuit).expect("keygen_vk should not fail");
+        let pk = keygen_pk(&params, vk.clone(), &empty_circuit).expect("keygen_pk should not fail");
+
+        let path = vec![a, b, c, d];
+        let directions = vec![false, false, true];
+        let root = merkle_root(path[0], &path[1..], &directions);
         let public_input = vec![root];
+        let circuit = MyCircuit { path, directions };
+
+        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        let proof = backend::prove::<KzgScheme, KzgProver, _>(
+            &params,
+            &pk,
+            &[circuit],
+            &[&[&[root]]],
+        )
+        .expect("proof generation should not fail");
+        println!("proof length:{}", proof.len());
+
+        let strategy = KzgStrategy::new(&params);
+        assert!(backend::verify::<KzgScheme, KzgVerifier, _>(
+            &params,
+            &vk,
+            strategy,
+            &[&[&public_input[..]]],
+            &proof,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn merkle_example1() {
+        let k = 8;
+
+        let a = Fp::from(1); // F[0]
+        let b = Fp::from(2); // F[1]
+        let c = Fp::from(7); // F[2]
+        let d = Fp::from(26); // F[3]
+
         let path = vec![a, b, c, d];
-        let circuit = MyCircuit { path };
+        let directions = vec![false, false, true];
+        let root = merkle_root(path[0], &path[1..], &directions);
+        let public_input = vec![root];
+        let circuit = MyCircuit { path, directions };
 
         let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
         prover.assert_satisfied();
@@ -335,16 +654,17 @@ mod tests {
 
     #[test]
     fn merkle_example_smaller() {
-        let k = 4;
+        let k = 8;
 
         let a = Fp::from(1); // F[0]
         let b = Fp::from(2); // F[1]
         let c = Fp::from(7); // F[2]
-        let root = Fp::from(10); // F[3]
 
         let path = vec![a, b, c];
+        let directions = vec![false, false];
+        let root = merkle_root(path[0], &path[1..], &directions);
         let public_input = vec![root];
-        let circuit = MyCircuit { path };
+        let circuit = MyCircuit { path, directions };
 
         let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
         prover.assert_satisfied();
@@ -354,17 +674,18 @@ mod tests {
 
     #[test]
     fn merkle_example_fails_on_wrong_root() {
-        let k = 4;
+        let k = 8;
 
         let a = Fp::from(1); // F[0]
         let b = Fp::from(2); // F[1]
         let c = Fp::from(7); // F[2]
         let d = Fp::from(26); // F[3]
-        let root = Fp::from(37); // F[4]     // correct is 36
 
-        let public_input = vec![root];
         let path = vec![a, b, c, d];
-        let circuit = MyCircuit { path };
+        let directions = vec![false, false, true];
+        let root = merkle_root(path[0], &path[1..], &directions) + Fp::one(); // wrong root
+        let public_input = vec![root];
+        let circuit = MyCircuit { path, directions };
 
         let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
 
@@ -377,20 +698,21 @@ mod tests {
     fn plot_merkle1() {
         use plotters::prelude::*;
 
-        let root = BitMapBackend::new("merkle-1-layout.png", (1024, 3096)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let root = root.titled("Merkle 1 Layout", ("sans-serif", 60)).unwrap();
+        let root_drawing = BitMapBackend::new("merkle-1-layout.png", (1024, 3096)).into_drawing_area();
+        root_drawing.fill(&WHITE).unwrap();
+        let root_drawing = root_drawing
+            .titled("Merkle 1 Layout", ("sans-serif", 60))
+            .unwrap();
         let a = Fp::from(1); // F[0]
         let b = Fp::from(2); // F[1]
         let c = Fp::from(7); // F[2]
         let d = Fp::from(26); // F[3]
-        let out = Fp::from(36); // F[4]
 
         let path = vec![a, b, c, d];
-        let public_input = vec![out];
-        let circuit = MyCircuit::<Fp> { path };
+        let directions = vec![false, false, true];
+        let circuit = MyCircuit::<Fp> { path, directions };
         halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
+            .render(8, &circuit, &root_drawing)
             .unwrap();
     }
 }